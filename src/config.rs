@@ -0,0 +1,184 @@
+use crate::{Cli, Opts};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Settings for the `watch` subcommand, read from the `[watch]` table in
+/// `leptos.toml`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// How long the source tree must be quiet before a rebuild is triggered,
+    /// in milliseconds.
+    pub debounce_ms: u64,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self { debounce_ms: 200 }
+    }
+}
+
+/// Settings for wrapping `cargo`/`wasm-pack`'s rustc invocations in a
+/// compiler cache, read from the `[cache]` table in `leptos.toml`.
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// `RUSTC_WRAPPER` binary to run, e.g. `sccache` or `cachepot`. Also
+    /// settable (and overridable) with `--cache-wrapper` on the CLI.
+    pub wrapper: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct Config {
+    /// Parsed from the CLI, not from `leptos.toml`.
+    #[serde(skip)]
+    pub cli: Opts,
+
+    pub index_path: PathBuf,
+    pub site_root: PathBuf,
+    pub site_pkg_dir: PathBuf,
+    pub style_file: PathBuf,
+
+    /// Build in Leptos' `experimental-islands` mode. Also settable (and
+    /// overridable) with `--islands` on the CLI.
+    #[serde(default)]
+    pub islands: bool,
+
+    #[serde(default)]
+    pub watch: WatchConfig,
+
+    #[serde(default)]
+    pub cache: CacheConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            cli: Opts::default(),
+            index_path: "index.html".into(),
+            site_root: "target/site".into(),
+            site_pkg_dir: "pkg".into(),
+            style_file: "style/main.scss".into(),
+            islands: false,
+            watch: WatchConfig::default(),
+            cache: CacheConfig::default(),
+        }
+    }
+}
+
+const DEFAULT_TOML: &str = r#"index_path = "index.html"
+site_root = "target/site"
+site_pkg_dir = "pkg"
+style_file = "style/main.scss"
+islands = false
+
+[watch]
+debounce_ms = 200
+"#;
+
+/// Path to the config file, relative to the current directory.
+pub const FILE_NAME: &str = "leptos.toml";
+
+pub fn read(_args: &Cli, cli: Opts) -> Result<Config> {
+    let mut config = parse()?;
+    config.islands |= cli.islands;
+    config.cache.wrapper = resolve_cache_wrapper(cli.cache_wrapper.clone().or(config.cache.wrapper.take()));
+    config.cli = cli;
+    Ok(config)
+}
+
+/// Re-reads and re-parses `leptos.toml`, keeping the CLI options from
+/// `current` since those never come from the file. Used by the config
+/// watcher to hot-reload a running `watch` session.
+pub fn reread(current: &Config) -> Result<Config> {
+    let mut config = parse()?;
+    config.islands |= current.cli.islands;
+    config.cache.wrapper = resolve_cache_wrapper(
+        current
+            .cli
+            .cache_wrapper
+            .clone()
+            .or(config.cache.wrapper.take()),
+    );
+    config.cli = current.cli.clone();
+    Ok(config)
+}
+
+fn parse() -> Result<Config> {
+    let text = fs::read_to_string(FILE_NAME)
+        .with_context(|| format!("could not read {FILE_NAME}. Run `cargo leptos init` to create one"))?;
+    toml::from_str(&text).with_context(|| format!("could not parse {FILE_NAME}"))
+}
+
+/// Checks that `wrapper` (if set) is actually on `PATH`, so that a stale or
+/// typo'd `cache.wrapper`/`--cache-wrapper` doesn't hard-fail every rustc
+/// invocation. Falls back to building without a compiler cache and logs a
+/// warning instead.
+fn resolve_cache_wrapper(wrapper: Option<String>) -> Option<String> {
+    let wrapper = wrapper?;
+    if wrapper_on_path(&wrapper) {
+        Some(wrapper)
+    } else {
+        tracing::warn!("cache wrapper `{wrapper}` not found on PATH, building without a compiler cache");
+        None
+    }
+}
+
+/// Mimics `which`: if `bin` contains a path separator it's checked directly,
+/// otherwise every directory on `PATH` is searched for a file by that name.
+/// Only existence is checked, not the executable bit.
+fn wrapper_on_path(bin: &str) -> bool {
+    let path = Path::new(bin);
+    if path.components().count() > 1 {
+        return path.is_file();
+    }
+    let Some(path_var) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path_var).any(|dir| dir.join(bin).is_file())
+}
+
+pub fn save_default_file() -> Result<()> {
+    fs::write("leptos.toml", DEFAULT_TOML).context("could not write leptos.toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::wrapper_on_path;
+
+    #[test]
+    fn wrapper_on_path_finds_bare_name_via_path_env() {
+        let dir = std::env::temp_dir().join(format!("cargo-leptos-test-bin-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("fake-wrapper"), "").unwrap();
+
+        let old_path = std::env::var_os("PATH");
+        std::env::set_var("PATH", std::env::join_paths([&dir]).unwrap());
+
+        assert!(wrapper_on_path("fake-wrapper"));
+        assert!(!wrapper_on_path("definitely-not-a-real-wrapper-binary"));
+
+        match old_path {
+            Some(path) => std::env::set_var("PATH", path),
+            None => std::env::remove_var("PATH"),
+        }
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn wrapper_on_path_checks_paths_with_separators_directly() {
+        let path = std::env::temp_dir().join(format!("cargo-leptos-test-bin2-{}", std::process::id()));
+        std::fs::write(&path, "").unwrap();
+
+        assert!(wrapper_on_path(path.to_str().unwrap()));
+        assert!(!wrapper_on_path(
+            path.with_file_name("definitely-not-a-real-wrapper-binary")
+                .to_str()
+                .unwrap()
+        ));
+
+        std::fs::remove_file(&path).ok();
+    }
+}
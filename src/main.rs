@@ -7,17 +7,20 @@ use clap::{Parser, Subcommand};
 use config::Config;
 use run::{cargo, reload, sass, serve, wasm_pack, watch, Html};
 use std::env;
+use std::time::Duration;
 use tokio::{
     signal,
     sync::{broadcast, RwLock},
 };
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Msg {
     /// sent by ctrl-c
     ShutDown,
     /// sent by fs watcher
     SrcChanged,
+    /// sent by the config watcher once `leptos.toml` has re-parsed cleanly
+    ConfigChanged(Config),
     /// messages sent to reload server (forwarded to browser)
     Reload(String),
 }
@@ -40,9 +43,26 @@ pub struct Opts {
     #[arg(long)]
     csr: bool,
 
+    /// Build in Leptos' `experimental-islands` mode: most of the page ships
+    /// as static SSR HTML, and only components marked as islands hydrate.
+    /// Overrides `islands` in `leptos.toml` when set.
+    #[arg(long)]
+    islands: bool,
+
+    /// Wrap cargo/wasm-pack's rustc invocations with a compiler cache (e.g.
+    /// `sccache`, `cachepot`) so the `csr`/`hydrate` and `ssr` target triples
+    /// share object files. Overrides `cache.wrapper` in `leptos.toml`.
+    #[arg(long)]
+    cache_wrapper: Option<String>,
+
     /// Verbosity (none: errors & warnings, -v: verbose, --vv: very verbose, --vvv: output everything)
     #[arg(short, action = clap::ArgAction::Count)]
     verbose: u8,
+
+    /// Emit logs as newline-delimited JSON instead of the default
+    /// human-readable format. Useful when piping into a log aggregator.
+    #[arg(long)]
+    log_json: bool,
 }
 
 #[derive(Debug, Parser)]
@@ -83,15 +103,20 @@ async fn main() -> Result<()> {
         | Commands::Test(opts)
         | Commands::Watch(opts) => opts,
     };
-    util::setup_logging(opts.verbose);
+    util::setup_logging(opts.verbose, opts.log_json);
 
     let config = config::read(&args, opts.clone())?;
 
     tokio::spawn(async {
         signal::ctrl_c().await.expect("failed to listen for event");
-        log::info!("Ctrl-c received");
+        tracing::info!("Ctrl-c received, shutting down (press again to force)");
         *SHUTDOWN.write().await = true;
-        MSG_BUS.send(Msg::ShutDown).unwrap();
+        // No receivers left just means every task has already exited.
+        let _ = MSG_BUS.send(Msg::ShutDown);
+
+        signal::ctrl_c().await.expect("failed to listen for event");
+        tracing::warn!("Ctrl-c received again, forcing exit");
+        std::process::exit(130);
     });
 
     match args.command {
@@ -99,17 +124,18 @@ async fn main() -> Result<()> {
         Commands::Build(_) => build_all(&config).await,
         Commands::Serve(_) => serve(&config).await,
         Commands::Test(_) => cargo::test(&config).await,
-        Commands::Watch(_) => watch(&config).await,
+        Commands::Watch(_) => watch(config).await,
     }
 }
 
 async fn send_reload() {
     if !*SHUTDOWN.read().await {
         if let Err(e) = MSG_BUS.send(Msg::Reload("reload".to_string())) {
-            log::error!("Failed to send reload: {e}");
+            tracing::error!("Failed to send reload: {e}");
         }
     }
 }
+#[tracing::instrument(name = "build", skip_all)]
 async fn build_csr_or_ssr(config: &Config) -> Result<()> {
     util::rm_dir_content("target/site")?;
     build_client(&config).await?;
@@ -134,6 +160,7 @@ async fn build_client(config: &Config) -> Result<()> {
     Ok(())
 }
 
+#[tracing::instrument(name = "build", skip_all)]
 async fn build_all(config: &Config) -> Result<()> {
     util::rm_dir_content("target/site")?;
 
@@ -163,29 +190,95 @@ async fn serve(config: &Config) -> Result<()> {
     }
 }
 
-async fn watch(config: &Config) -> Result<()> {
+/// Why a `watch_session` ended.
+enum WatchOutcome {
+    ShutDown,
+    /// `leptos.toml` was edited and re-parsed successfully; restart the
+    /// whole session with the new config instead of tearing the process down.
+    ConfigChanged(Config),
+}
+
+/// Runs `watch` sessions back to back, swapping in a freshly parsed `Config`
+/// whenever `leptos.toml` changes instead of requiring a restart.
+async fn watch(mut config: Config) -> Result<()> {
+    loop {
+        match watch_session(config).await? {
+            WatchOutcome::ShutDown => return Ok(()),
+            WatchOutcome::ConfigChanged(new_config) => {
+                tracing::info!("leptos.toml reloaded, restarting watch session");
+                config = new_config;
+            }
+        }
+    }
+}
+
+#[tracing::instrument(name = "watch", skip_all)]
+async fn watch_session(config: Config) -> Result<WatchOutcome> {
     let cfg = config.clone();
-    let _ = tokio::spawn(async move { watch::run(cfg).await });
+    let watch_task = tokio::spawn(async move { watch::run(cfg).await });
 
-    if config.cli.csr {
+    let cfg = config.clone();
+    let config_watch_task = tokio::spawn(async move { watch::run_config(cfg).await });
+
+    let serve_task = if config.cli.csr {
         let cfg = config.clone();
-        let _ = tokio::spawn(async move { serve::run(&cfg).await });
-    }
+        Some(tokio::spawn(async move { serve::run(&cfg).await }))
+    } else {
+        None
+    };
 
     reload::run(&config).await?;
 
-    loop {
-        build_csr_or_ssr(config).await?;
+    // Coalesces bursts of fs events into one rebuild, and makes sure a
+    // rebuild in progress never gets interrupted by its own follow-ups.
+    let debouncer = watch::Debouncer::new(Duration::from_millis(config.watch.debounce_ms));
+    let debouncer_task = debouncer.spawn();
+
+    let mut bus = MSG_BUS.subscribe();
 
+    let outcome = loop {
+        build_csr_or_ssr(&config).await?;
+        debouncer.finished_rebuild();
         send_reload().await;
-        if config.cli.csr {
-            MSG_BUS.subscribe().recv().await?;
-        } else {
-            cargo::run(&config).await?;
+
+        if *SHUTDOWN.read().await {
+            break WatchOutcome::ShutDown;
+        }
+
+        tokio::select! {
+            _ = debouncer.wait_for_rebuild(), if config.cli.csr => {}
+            res = cargo::run_watched(&config, &debouncer), if !config.cli.csr => res?,
+            new_config = wait_for_config_change(&mut bus) => {
+                match new_config {
+                    Some(new_config) => break WatchOutcome::ConfigChanged(new_config),
+                    None => break WatchOutcome::ShutDown,
+                }
+            }
         }
+
         if *SHUTDOWN.read().await {
-            break;
+            break WatchOutcome::ShutDown;
+        }
+    };
+
+    watch_task.abort();
+    config_watch_task.abort();
+    debouncer_task.abort();
+    if let Some(serve_task) = serve_task {
+        serve_task.abort();
+    }
+    Ok(outcome)
+}
+
+/// Waits until `leptos.toml` has been reloaded, returning the new config, or
+/// `None` if we should shut down instead.
+async fn wait_for_config_change(bus: &mut broadcast::Receiver<Msg>) -> Option<Config> {
+    loop {
+        match bus.recv().await {
+            Ok(Msg::ConfigChanged(config)) => return Some(config),
+            Ok(Msg::ShutDown) => return None,
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return None,
         }
     }
-    Ok(())
 }
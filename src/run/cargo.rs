@@ -0,0 +1,92 @@
+use crate::config::Config;
+use crate::run::watch::Debouncer;
+use crate::util;
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use tokio::process::{Child, Command};
+
+fn command(config: &Config, cmd: &str) -> Command {
+    let mut command = Command::new("cargo");
+    command.arg(cmd);
+    if config.cli.release {
+        command.arg("--release");
+    }
+    if config.islands {
+        command.args(["--features", "experimental-islands"]);
+    }
+    if let Some(wrapper) = &config.cache.wrapper {
+        command.env("RUSTC_WRAPPER", wrapper);
+    }
+    command
+}
+
+#[tracing::instrument(name = "cargo", skip_all)]
+pub async fn build(config: &Config) -> Result<()> {
+    util::run_piped(command(config, "build"), "cargo build").await
+}
+
+#[tracing::instrument(name = "cargo", skip_all)]
+pub async fn test(config: &Config) -> Result<()> {
+    util::run_piped(command(config, "test"), "cargo test").await
+}
+
+/// Runs the server binary to completion. Used by the plain `serve`
+/// subcommand, where there's no watcher to interrupt it. Terminated
+/// gracefully (see [`util::run_cancellable`]) if a shutdown is signalled
+/// mid-run instead of being left to hold the port.
+#[tracing::instrument(name = "cargo", skip_all)]
+pub async fn run(config: &Config) -> Result<()> {
+    let child = util::spawn_piped(command(config, "run"), "cargo run")?;
+    match util::run_cancellable(child, "cargo run").await? {
+        // `None` means a shutdown was signalled and the child was already
+        // terminated gracefully; that's not a failure worth reporting.
+        Some(status) if !status.success() => anyhow::bail!("cargo run exited with {status}"),
+        _ => Ok(()),
+    }
+}
+
+/// Gracefully (SIGTERM, then SIGKILL after [`util::SHUTDOWN_TIMEOUT`])
+/// terminates the wrapped child if it's dropped before it exited on its own,
+/// e.g. because the `watch` loop's `select!` picked a different branch (a
+/// `leptos.toml` reload, or a shutdown) and gave up on waiting for it.
+struct KillOnDrop(Option<Child>);
+
+impl KillOnDrop {
+    fn child(&mut self) -> &mut Child {
+        self.0.as_mut().expect("child taken twice")
+    }
+
+    async fn terminate(mut self) {
+        if let Some(child) = self.0.take() {
+            util::terminate_child(child, util::SHUTDOWN_TIMEOUT).await;
+        }
+    }
+}
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        let Some(child) = self.0.take() else { return };
+        // Drop can't be async, so hand the graceful-then-forceful shutdown
+        // off to a detached task rather than blocking here.
+        tokio::spawn(util::terminate_child(child, util::SHUTDOWN_TIMEOUT));
+    }
+}
+
+/// Runs the server binary, but gives it up and returns as soon as the
+/// debouncer reports a coalesced source change, so the watch loop can
+/// rebuild. Killed automatically if cancelled for any other reason (see
+/// [`KillOnDrop`]).
+#[tracing::instrument(name = "cargo", skip_all)]
+pub async fn run_watched(config: &Config, debouncer: &Arc<Debouncer>) -> Result<()> {
+    let child = util::spawn_piped(command(config, "run"), "cargo run")?;
+    let mut child = KillOnDrop(Some(child));
+
+    tokio::select! {
+        status = child.child().wait() => {
+            status.context("cargo run failed")?;
+        }
+        _ = debouncer.wait_for_rebuild() => child.terminate().await,
+        _ = util::wait_for_shutdown() => child.terminate().await,
+    }
+    Ok(())
+}
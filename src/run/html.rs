@@ -0,0 +1,60 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// The project's `index.html`, parsed so the hydration/bootstrap `<script>`
+/// can be generated for either the `cargo build` artifact (server rendered)
+/// or the `wasm-pack` artifact (client side rendered).
+pub struct Html {
+    source: String,
+}
+
+impl Html {
+    pub fn read(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("could not read {}", path.display()))?;
+        Ok(Self { source })
+    }
+
+    /// Writes `target/site/index.html` with a `<script type="module">` that
+    /// boots the wasm bundle directly in the browser (`--csr` mode).
+    pub fn generate_html(&self, config: &Config) -> Result<()> {
+        self.write(config, &bootstrap_script(config, "mount_to_body"))
+    }
+
+    /// Writes the `index.html` served by the `ssr` binary. In ordinary SSR
+    /// mode the hydration script is generated by `leptos::leptos_axum`/
+    /// `leptos_actix` at runtime, so the static file is left untouched; in
+    /// `--islands` mode only the islands marked interactive in app code ship
+    /// and there's no runtime-rendered hydration script to rely on, so the
+    /// `hydrate_islands` bootstrap is baked into the static file here.
+    pub fn generate_rust(&self, config: &Config) -> Result<()> {
+        if !config.islands {
+            let out = config.site_root.join("index.html");
+            return std::fs::write(&out, &self.source)
+                .with_context(|| format!("could not write {}", out.display()));
+        }
+        self.write(config, &bootstrap_script(config, "hydrate_islands"))
+    }
+
+    fn write(&self, config: &Config, script: &str) -> Result<()> {
+        let out = config.site_root.join("index.html");
+        let html = self.source.replacen("</body>", &format!("{script}</body>"), 1);
+        std::fs::write(&out, html).with_context(|| format!("could not write {}", out.display()))
+    }
+}
+
+/// The wasm-bindgen generated `pkg/app.js` (`wasm_pack::build` passes
+/// `--out-name app`) exports `init` plus whichever
+/// hydration entry points the build was compiled with.
+fn bootstrap_script(config: &Config, entry: &str) -> String {
+    let pkg = config.site_pkg_dir.display();
+    format!(
+        r#"<script type="module">
+    import init, {{ {entry} }} from "/{pkg}/app.js";
+    await init();
+    {entry}();
+</script>
+"#
+    )
+}
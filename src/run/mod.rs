@@ -0,0 +1,9 @@
+pub mod cargo;
+mod html;
+pub mod reload;
+pub mod sass;
+pub mod serve;
+pub mod wasm_pack;
+pub mod watch;
+
+pub use html::Html;
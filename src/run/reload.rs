@@ -0,0 +1,35 @@
+use crate::config::Config;
+use crate::MSG_BUS;
+use anyhow::Result;
+use futures::{SinkExt, StreamExt};
+use std::net::SocketAddr;
+use tokio::sync::broadcast::error::RecvError;
+use warp::ws::Ws;
+use warp::Filter;
+
+/// Runs the websocket server the browser-side reload script connects to.
+/// Forwards `Msg::Reload` from `MSG_BUS` on to every connected socket.
+#[tracing::instrument(name = "reload", skip_all)]
+pub async fn run(_config: &Config) -> Result<()> {
+    let reload = warp::path("reload").and(warp::ws()).map(|ws: Ws| {
+        ws.on_upgrade(|socket| async move {
+            let (mut tx, _rx) = socket.split();
+            let mut bus = MSG_BUS.subscribe();
+            loop {
+                match bus.recv().await {
+                    Ok(crate::Msg::Reload(msg)) => {
+                        if tx.send(warp::ws::Message::text(msg)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    });
+
+    let addr: SocketAddr = ([127, 0, 0, 1], 3001).into();
+    tokio::spawn(warp::serve(reload).run(addr));
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use crate::config::Config;
+use crate::util;
+use anyhow::Result;
+use tokio::process::Command;
+
+/// Compiles `config.style_file` into `target/site/pkg/app.css`.
+#[tracing::instrument(name = "sass", skip_all)]
+pub async fn run(config: &Config) -> Result<()> {
+    let out = config.site_root.join(&config.site_pkg_dir).join("app.css");
+    let mut cmd = Command::new("sass");
+    cmd.arg(&config.style_file).arg(&out);
+    util::run_piped(cmd, "sass").await
+}
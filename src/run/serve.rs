@@ -0,0 +1,18 @@
+use crate::config::Config;
+use crate::util;
+use anyhow::Result;
+use std::net::SocketAddr;
+
+/// Serves `target/site` as static files. Only used in `--csr` mode, where
+/// there's no server binary of the user's own to run. Shuts down (releasing
+/// the port) as soon as `Msg::ShutDown` is broadcast, rather than running
+/// until the process is killed.
+#[tracing::instrument(name = "serve", skip_all)]
+pub async fn run(config: &Config) -> Result<()> {
+    let addr: SocketAddr = ([127, 0, 0, 1], 3000).into();
+    let files = warp::fs::dir(config.site_root.clone());
+    tracing::info!("serving {} on http://{addr}", config.site_root.display());
+    let (_, server) = warp::serve(files).bind_with_graceful_shutdown(addr, util::wait_for_shutdown());
+    server.await;
+    Ok(())
+}
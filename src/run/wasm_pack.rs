@@ -0,0 +1,40 @@
+use crate::config::Config;
+use crate::util;
+use anyhow::Result;
+use tokio::process::Command;
+
+/// Builds the client side wasm bundle with `wasm-pack`. In `--csr` mode this
+/// produces a browser-runnable bundle; otherwise it produces the `hydrate`
+/// feature build that pairs with the server rendered `cargo::build` output.
+/// Terminated gracefully (see [`util::run_cancellable`]) rather than left to
+/// finish if a shutdown is signalled mid-build.
+#[tracing::instrument(name = "wasm_pack", skip_all)]
+pub async fn build(config: &Config) -> Result<()> {
+    let mut cmd = Command::new("wasm-pack");
+    cmd.args(["build", "--target", "web", "--out-dir"])
+        .arg(config.site_root.join(&config.site_pkg_dir))
+        .args(["--out-name", "app"]);
+
+    if config.cli.release {
+        cmd.arg("--release");
+    }
+
+    cmd.arg("--").args(["--no-default-features"]);
+    let mut features = if config.cli.csr { "csr" } else { "hydrate" }.to_string();
+    if config.islands {
+        features.push_str(",experimental-islands");
+    }
+    cmd.arg("--features").arg(features);
+
+    if let Some(wrapper) = &config.cache.wrapper {
+        cmd.env("RUSTC_WRAPPER", wrapper);
+    }
+
+    let child = util::spawn_piped(cmd, "wasm-pack")?;
+    match util::run_cancellable(child, "wasm-pack").await? {
+        // `None` means a shutdown was signalled and the child was already
+        // terminated gracefully; that's not a failure worth reporting.
+        Some(status) if !status.success() => anyhow::bail!("wasm-pack exited with {status}"),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,265 @@
+use crate::config::Config;
+use crate::{Msg, MSG_BUS};
+use anyhow::{Context, Result};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tokio::sync::Notify;
+
+/// Watches the project's source tree and forwards every relevant fs event as
+/// a raw `Msg::SrcChanged` on `MSG_BUS`. No debouncing happens here; that's
+/// [`Debouncer`]'s job, so that a single save (or an editor's
+/// write-then-rename) doesn't fan out into a rebuild per event.
+///
+/// The fs watcher's `std::sync::mpsc::Receiver` has no `.await` point, so the
+/// blocking loop runs on [`tokio::task::spawn_blocking`]'s dedicated thread
+/// pool rather than pinning one of the (often only one or two) async worker
+/// threads for as long as this watch session lasts.
+#[tracing::instrument(name = "watch", skip_all)]
+pub async fn run(config: Config) -> Result<()> {
+    tracing::info!("watching for changes (site root: {})", config.site_root.display());
+
+    let span = tracing::Span::current();
+    tokio::task::spawn_blocking(move || {
+        let _guard = span.enter();
+        run_blocking()
+    })
+    .await
+    .context("watch task panicked")?
+}
+
+fn run_blocking() -> Result<()> {
+    let (tx, rx) = channel();
+    let mut watcher: RecommendedWatcher =
+        notify::recommended_watcher(tx).context("could not create fs watcher")?;
+    watcher
+        .watch(std::path::Path::new("."), RecursiveMode::Recursive)
+        .context("could not watch source directory")?;
+
+    for res in rx {
+        match res {
+            Ok(event) if is_relevant(&event) => {
+                // Fails only once every receiver (the debouncer, the reload
+                // server) has gone away, which means we're shutting down.
+                if MSG_BUS.send(Msg::SrcChanged).is_err() {
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("watch error: {e}"),
+        }
+    }
+    Ok(())
+}
+
+fn is_relevant(event: &notify::Event) -> bool {
+    use notify::EventKind::*;
+    matches!(event.kind, Create(_) | Modify(_) | Remove(_))
+        && (event.paths.is_empty() || !event.paths.iter().all(|p| is_build_output(p)))
+}
+
+/// Whether `path` lies under `./target`, where the build itself writes
+/// (`target/site` and cargo's and wasm-pack's artifacts).
+fn is_build_output(path: &std::path::Path) -> bool {
+    let target = std::path::Path::new("target");
+    path.strip_prefix(".").unwrap_or(path).starts_with(target)
+        || std::env::current_dir().map_or(false, |cwd| path.starts_with(cwd.join(target)))
+}
+
+/// Watches `leptos.toml` itself and broadcasts `Msg::ConfigChanged` whenever
+/// it re-parses cleanly, so editing the site root, sass entry or index path
+/// doesn't require killing and re-running `cargo leptos watch`. The serve
+/// and reload ports are fixed and not read from `leptos.toml`. A
+/// save that leaves the file mid-edit (and therefore invalid TOML) is logged
+/// and otherwise ignored; the watch session keeps running on the old config.
+///
+/// Like [`run`], the blocking fs-watcher loop runs on
+/// [`tokio::task::spawn_blocking`]'s pool so it doesn't pin an async worker
+/// thread, and so `abort()`-ing the caller's `JoinHandle` actually takes
+/// effect at the `.await` below instead of never getting polled again.
+#[tracing::instrument(name = "config_watch", skip_all)]
+pub async fn run_config(current: Config) -> Result<()> {
+    let span = tracing::Span::current();
+    tokio::task::spawn_blocking(move || {
+        let _guard = span.enter();
+        run_config_blocking(&current)
+    })
+    .await
+    .context("config watch task panicked")?
+}
+
+fn run_config_blocking(current: &Config) -> Result<()> {
+    let (tx, rx) = channel();
+    let mut watcher: RecommendedWatcher =
+        notify::recommended_watcher(tx).context("could not create fs watcher")?;
+    watcher
+        .watch(
+            std::path::Path::new(crate::config::FILE_NAME),
+            RecursiveMode::NonRecursive,
+        )
+        .context("could not watch leptos.toml")?;
+
+    for res in rx {
+        match res {
+            Ok(event) if is_relevant(&event) => match crate::config::reread(current) {
+                Ok(new_config) => {
+                    tracing::info!("leptos.toml changed, reloading");
+                    if MSG_BUS.send(Msg::ConfigChanged(new_config)).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => tracing::warn!("leptos.toml is invalid, keeping previous config: {e:#}"),
+            },
+            Ok(_) => {}
+            Err(e) => tracing::warn!("config watch error: {e}"),
+        }
+    }
+    Ok(())
+}
+
+/// Sits between the fs watcher and the rebuild loop. Coalesces bursts of
+/// `Msg::SrcChanged` into a single rebuild signal once the bus has been
+/// quiet for the configured debounce period, and guarantees single-flight
+/// semantics: a rebuild already in progress is never interrupted, and at
+/// most one follow-up rebuild is queued for when it finishes.
+pub struct Debouncer {
+    debounce: Duration,
+    building: AtomicBool,
+    dirty: AtomicBool,
+    notify: Notify,
+}
+
+impl Debouncer {
+    pub fn new(debounce: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            debounce,
+            building: AtomicBool::new(false),
+            dirty: AtomicBool::new(false),
+            notify: Notify::new(),
+        })
+    }
+
+    /// Spawns the task that listens on `MSG_BUS` and turns `Msg::SrcChanged`
+    /// bursts into calls of `notify`. Returns the `JoinHandle` so the caller
+    /// can abort it once the session it belongs to ends.
+    pub fn spawn(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let this = self.clone();
+        tokio::spawn(async move { this.listen().await })
+    }
+
+    async fn listen(self: Arc<Self>) {
+        let mut rx = MSG_BUS.subscribe();
+        loop {
+            match rx.recv().await {
+                // A lagged receiver skipped messages during a burst of
+                // changes, so treat it as one more change.
+                Ok(Msg::SrcChanged) | Err(RecvError::Lagged(_)) => {
+                    if self.building.load(Ordering::SeqCst) {
+                        // Already rebuilding: remember there's more to do
+                        // instead of restarting or queueing another signal.
+                        self.dirty.store(true, Ordering::SeqCst);
+                        continue;
+                    }
+                    self.debounce_and_fire(&mut rx).await;
+                }
+                Ok(Msg::ShutDown) => break,
+                Ok(_) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// Resets the debounce timer on every further change, firing only once
+    /// the bus has gone quiet for `self.debounce`.
+    async fn debounce_and_fire(&self, rx: &mut Receiver<Msg>) {
+        loop {
+            match tokio::time::timeout(self.debounce, rx.recv()).await {
+                Ok(Ok(Msg::SrcChanged)) => continue,
+                Ok(Ok(Msg::ShutDown)) => return,
+                Ok(Ok(_)) | Ok(Err(RecvError::Lagged(_))) => continue,
+                Ok(Err(RecvError::Closed)) => return,
+                Err(_) => break, // quiet for `self.debounce`: go rebuild
+            }
+        }
+        self.notify.notify_one();
+    }
+
+    /// Waits for the next coalesced rebuild signal. Marks a rebuild as in
+    /// progress so further changes are coalesced rather than queued.
+    pub async fn wait_for_rebuild(&self) {
+        self.notify.notified().await;
+        self.building.store(true, Ordering::SeqCst);
+    }
+
+    /// Call once the triggered rebuild has finished. If a change arrived
+    /// mid-build, fires exactly one immediate follow-up rebuild.
+    pub fn finished_rebuild(&self) {
+        self.building.store(false, Ordering::SeqCst);
+        if self.dirty.swap(false, Ordering::SeqCst) {
+            self.notify.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Debouncer;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn wait_for_rebuild_marks_building() {
+        let debouncer = Debouncer::new(Duration::from_millis(10));
+        debouncer.notify.notify_one();
+
+        debouncer.wait_for_rebuild().await;
+
+        assert!(debouncer.building.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn finished_rebuild_without_dirty_does_not_requeue() {
+        let debouncer = Debouncer::new(Duration::from_millis(10));
+        debouncer.notify.notify_one();
+        debouncer.wait_for_rebuild().await;
+
+        debouncer.finished_rebuild();
+
+        assert!(!debouncer.building.load(Ordering::SeqCst));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), debouncer.wait_for_rebuild())
+                .await
+                .is_err(),
+            "no rebuild should be queued when nothing changed mid-build"
+        );
+    }
+
+    #[tokio::test]
+    async fn finished_rebuild_with_dirty_fires_exactly_one_followup() {
+        let debouncer = Debouncer::new(Duration::from_millis(10));
+        debouncer.notify.notify_one();
+        debouncer.wait_for_rebuild().await;
+
+        // A source change arrived while the (simulated) rebuild was running.
+        debouncer.dirty.store(true, Ordering::SeqCst);
+        debouncer.finished_rebuild();
+
+        tokio::time::timeout(Duration::from_millis(20), debouncer.wait_for_rebuild())
+            .await
+            .expect("a follow-up rebuild should fire immediately");
+        assert!(debouncer.building.load(Ordering::SeqCst));
+
+        // The dirty flag was consumed by the single follow-up, so finishing
+        // again without a further change doesn't queue a second one.
+        debouncer.finished_rebuild();
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), debouncer.wait_for_rebuild())
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{Msg, MSG_BUS};
+use anyhow::{Context, Result};
+#[cfg(unix)]
+use nix::sys::signal::{self, Signal};
+#[cfg(unix)]
+use nix::unistd::Pid;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::Instrument;
+use tracing_subscriber::EnvFilter;
+
+/// Configures the `tracing` backend based on the `-v` count passed on the
+/// CLI. Spans (one per subsystem: `cargo`, `wasm_pack`, `sass`, `watch`, ...)
+/// are included in the output so a busy `watch` session's interleaved log
+/// lines can still be told apart.
+///
+/// `-v`/`-vv`/`-vvv` only set the *default* filter; `RUST_LOG` is honored
+/// when set, so e.g. `RUST_LOG=cargo_leptos=debug,warn` still works for
+/// per-target filtering without passing `-vv`. Pass `json` (`--log-json`)
+/// to switch to newline-delimited JSON output for log aggregators.
+pub fn setup_logging(verbose: u8, json: bool) {
+    let default_level = match verbose {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    };
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_level));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE);
+    if json {
+        builder.json().init();
+    } else {
+        builder.init();
+    }
+}
+
+/// Spawns `cmd` with stdout/stderr piped and forwarded as `tracing` events
+/// under the caller's current span (e.g. `cargo`, `wasm_pack`, `sass`),
+/// instead of being inherited straight to the terminal, where two
+/// subprocesses running concurrently (e.g. during `watch`) would otherwise
+/// interleave with no way to tell which line came from which stage. Returns
+/// the `Child` so callers can still `.wait()` it directly or hand it to
+/// [`run_cancellable`]/[`terminate_child`].
+pub fn spawn_piped(mut cmd: Command, label: &str) -> Result<Child> {
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut child = cmd.spawn().with_context(|| format!("could not run {label}"))?;
+    if let Some(stdout) = child.stdout.take() {
+        tokio::spawn(stream_output(stdout).in_current_span());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        tokio::spawn(stream_output(stderr).in_current_span());
+    }
+    Ok(child)
+}
+
+/// Forwards every line read from `reader` as a `tracing` event in whatever
+/// span was active when the reader was spawned.
+async fn stream_output(reader: impl tokio::io::AsyncRead + Unpin) {
+    let mut lines = BufReader::new(reader).lines();
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => tracing::info!("{line}"),
+            Ok(None) | Err(_) => break,
+        }
+    }
+}
+
+/// Runs `cmd` to completion with its output captured (see [`spawn_piped`]),
+/// erroring out if it didn't exit successfully.
+pub async fn run_piped(cmd: Command, label: &str) -> Result<()> {
+    let mut child = spawn_piped(cmd, label)?;
+    let status = child.wait().await.with_context(|| format!("{label} failed"))?;
+    if !status.success() {
+        anyhow::bail!("{label} exited with {status}");
+    }
+    Ok(())
+}
+
+/// Removes everything inside `path`, creating it first if it doesn't exist.
+pub fn rm_dir_content(path: &str) -> Result<()> {
+    let dir = Path::new(path);
+    if !dir.exists() {
+        std::fs::create_dir_all(dir).with_context(|| format!("could not create {path}"))?;
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir).with_context(|| format!("could not read {path}"))? {
+        let entry = entry?;
+        if entry.path().is_dir() {
+            std::fs::remove_dir_all(entry.path())?;
+        } else {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Resolves once a `Msg::ShutDown` has been broadcast.
+pub async fn wait_for_shutdown() {
+    let mut rx = MSG_BUS.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(Msg::ShutDown) => return,
+            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+/// How long a terminated child is given to exit cleanly before it's SIGKILLed.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Asks `child` to exit with SIGTERM, gives it `timeout` to do so, then
+/// SIGKILLs it if it's still around. Used to drain long-lived subprocesses
+/// (`cargo run`, `wasm-pack`) on shutdown instead of leaving orphans holding
+/// the port. On non-Unix platforms there's no graceful-signal equivalent, so
+/// the child is killed outright (`TerminateProcess` via [`Child::start_kill`]).
+pub async fn terminate_child(mut child: Child, timeout: Duration) {
+    #[cfg(unix)]
+    {
+        let Some(pid) = child.id() else { return };
+        if signal::kill(Pid::from_raw(pid as i32), Signal::SIGTERM).is_err() {
+            // process is already gone
+            return;
+        }
+        if tokio::time::timeout(timeout, child.wait()).await.is_err() {
+            tracing::warn!("pid {pid} did not exit within {timeout:?} of SIGTERM, sending SIGKILL");
+            let _ = child.start_kill();
+            let _ = child.wait().await;
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = child.start_kill();
+        let _ = child.wait().await;
+    }
+}
+
+/// Runs `child` to completion, unless a shutdown is signalled first, in
+/// which case it's gracefully terminated (see [`terminate_child`]) and
+/// `None` is returned instead of `child`'s exit status, so a Ctrl-C landing
+/// mid-build doesn't get reported back up as a failure. Callers decide
+/// whether a non-`None` status that isn't a success should bail.
+pub async fn run_cancellable(mut child: Child, label: &str) -> Result<Option<std::process::ExitStatus>> {
+    let status = tokio::select! {
+        status = child.wait() => Some(status),
+        _ = wait_for_shutdown() => None,
+    };
+
+    match status {
+        Some(status) => {
+            let status = status.with_context(|| format!("{label} failed"))?;
+            Ok(Some(status))
+        }
+        None => {
+            terminate_child(child, SHUTDOWN_TIMEOUT).await;
+            Ok(None)
+        }
+    }
+}